@@ -7,12 +7,32 @@
 //! - [`EnvJailer`]: Extends [`Jailer`] by also managing environment variables,
 //!   allowing preservation of selected variables while clearing others on exit.
 //!
-//! Both types are thread-safe and ensure only one instance runs at a time via
-//! a global mutex.
+//! Both types are thread-safe and ensure only one thread mutates the current
+//! directory or environment at a time via a global, reentrant lock. Both
+//! also expose a [`JailedCommand`] builder via `command()`, so child
+//! processes can be spawned with their working directory (and, for
+//! [`EnvJailer`], their environment) pinned to the jail.
+//!
+//! [`push_dir`] and [`push_env`] provide composable, stack-based scopes —
+//! temporary directory or single-variable overrides, released on drop — that
+//! nest inside (or outside) a [`Jailer`]/[`EnvJailer`] without deadlocking.
+//!
+//! Both [`Jailer`] and [`EnvJailer`] are generic over a [`SystemEnv`]
+//! implementation, defaulting to the real, OS-backed [`StdSystemEnv`]. Swap
+//! in [`TestSystemEnv`] to unit-test restore/cleanup logic against an
+//! in-memory virtual directory and environment instead.
+//!
+//! [`JailerBuilder::track_changes`] opts a [`Jailer`] into recording a
+//! snapshot of the temp directory on creation, so [`Jailer::changes`] and
+//! [`Jailer::close_with_changes`] can later report an [`FsDiff`] of files
+//! created, modified, or removed during the session.
 
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::ffi::{OsStr, OsString};
-use std::path::PathBuf;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Output};
 use std::sync::{Arc, OnceLock};
 
 use parking_lot::lock_api::ArcMutexGuard;
@@ -27,26 +47,281 @@ fn initialize_or_get_mutex<'a>() -> &'a Arc<Mutex<()>> {
     MUTEX.get_or_init(|| Arc::new(Mutex::new(())))
 }
 
+thread_local! {
+    /// Number of nested jails/scopes currently held on this thread.
+    static LOCK_DEPTH: Cell<u32> = const { Cell::new(0) };
+    /// The single [`ArcMutexGuard`] held for the outermost jail/scope on this
+    /// thread, shared by every nested one.
+    static HELD_LOCK: RefCell<Option<ArcMutexGuard<RawMutex, ()>>> = const { RefCell::new(None) };
+}
+
+/// Handle representing one level of reentrant ownership of the global lock.
+///
+/// The first handle acquired on a thread actually locks [`MUTEX`]; further
+/// handles acquired on the same thread are free, since the global lock is
+/// already held by that thread. Handles must be dropped in strict LIFO order
+/// relative to other scopes guarded by [`acquire_reentrant_lock`] on the same
+/// thread; the last handle to drop releases the underlying lock.
+struct ReentrantLockGuard {
+    _private: (),
+    // The lock depth/guard are tracked per-thread; moving a `ReentrantLockGuard`
+    // to another thread and dropping it there would decrement *that* thread's
+    // (zero-valued) depth counter instead of the thread that acquired it. Make
+    // the guard `!Send` so the compiler rejects that instead of panicking or
+    // silently corrupting the depth counter at runtime.
+    _not_send: PhantomData<*const ()>,
+}
+
+/// Acquire one reentrant level of the global lock for the current thread.
+///
+/// Unlike locking [`MUTEX`] directly, calling this while the current thread
+/// already holds a [`ReentrantLockGuard`] does not deadlock: the existing
+/// lock is shared and a depth counter tracks how many levels are active.
+/// Another thread calling this still blocks until every level on the holding
+/// thread has been released.
+fn acquire_reentrant_lock() -> ReentrantLockGuard {
+    LOCK_DEPTH.with(|depth| {
+        if depth.get() == 0 {
+            let guard = initialize_or_get_mutex().lock_arc();
+            HELD_LOCK.with(|held| *held.borrow_mut() = Some(guard));
+        }
+        depth.set(depth.get() + 1);
+    });
+    ReentrantLockGuard {
+        _private: (),
+        _not_send: PhantomData,
+    }
+}
+
+impl Drop for ReentrantLockGuard {
+    fn drop(&mut self) {
+        LOCK_DEPTH.with(|depth| {
+            let remaining = depth.get() - 1;
+            depth.set(remaining);
+            if remaining == 0 {
+                HELD_LOCK.with(|held| {
+                    held.borrow_mut().take();
+                });
+            }
+        });
+    }
+}
+
+/// Abstraction over the current-directory and environment-variable
+/// primitives a [`Jailer`]/[`EnvJailer`] needs, so their restore-and-cleanup
+/// logic can be exercised against an in-memory implementation instead of the
+/// real process.
+///
+/// [`StdSystemEnv`] is the default, OS-backed implementation used by
+/// [`Jailer::new`]/[`EnvJailer::new`]. [`TestSystemEnv`] is an in-memory
+/// implementation for deterministic, concurrency-safe unit tests.
+pub trait SystemEnv {
+    /// Mirrors [`std::env::current_dir`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the current directory cannot be determined.
+    fn current_dir(&self) -> Result<PathBuf, std::io::Error>;
+
+    /// Mirrors [`std::env::set_current_dir`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the current directory cannot be changed.
+    fn set_current_dir(&self, path: &Path) -> Result<(), std::io::Error>;
+
+    /// Mirrors [`std::env::vars_os`].
+    fn vars_os(&self) -> HashMap<OsString, OsString>;
+
+    /// Mirrors [`std::env::var_os`].
+    fn var_os(&self, key: &OsStr) -> Option<OsString>;
+
+    /// Mirrors [`std::env::set_var`].
+    ///
+    /// # Safety
+    ///
+    /// Same caveats as [`std::env::set_var`]: setting an environment
+    /// variable is not thread-safe with respect to other code reading or
+    /// writing the environment concurrently.
+    unsafe fn set_var(&self, key: &OsStr, value: &OsStr);
+
+    /// Mirrors [`std::env::remove_var`].
+    ///
+    /// # Safety
+    ///
+    /// Same caveats as [`std::env::remove_var`].
+    unsafe fn remove_var(&self, key: &OsStr);
+}
+
+/// The default, OS-backed [`SystemEnv`] implementation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StdSystemEnv;
+
+impl SystemEnv for StdSystemEnv {
+    fn current_dir(&self) -> Result<PathBuf, std::io::Error> {
+        std::env::current_dir()
+    }
+
+    fn set_current_dir(&self, path: &Path) -> Result<(), std::io::Error> {
+        std::env::set_current_dir(path)
+    }
+
+    fn vars_os(&self) -> HashMap<OsString, OsString> {
+        std::env::vars_os().collect()
+    }
+
+    fn var_os(&self, key: &OsStr) -> Option<OsString> {
+        std::env::var_os(key)
+    }
+
+    unsafe fn set_var(&self, key: &OsStr, value: &OsStr) {
+        unsafe {
+            std::env::set_var(key, value);
+        }
+    }
+
+    unsafe fn remove_var(&self, key: &OsStr) {
+        unsafe {
+            std::env::remove_var(key);
+        }
+    }
+}
+
+impl<T> SystemEnv for Arc<T>
+where
+    T: SystemEnv + ?Sized,
+{
+    fn current_dir(&self) -> Result<PathBuf, std::io::Error> {
+        (**self).current_dir()
+    }
+
+    fn set_current_dir(&self, path: &Path) -> Result<(), std::io::Error> {
+        (**self).set_current_dir(path)
+    }
+
+    fn vars_os(&self) -> HashMap<OsString, OsString> {
+        (**self).vars_os()
+    }
+
+    fn var_os(&self, key: &OsStr) -> Option<OsString> {
+        (**self).var_os(key)
+    }
+
+    unsafe fn set_var(&self, key: &OsStr, value: &OsStr) {
+        unsafe {
+            (**self).set_var(key, value);
+        }
+    }
+
+    unsafe fn remove_var(&self, key: &OsStr) {
+        unsafe {
+            (**self).remove_var(key);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TestSystemEnvState {
+    current_dir: PathBuf,
+    vars: HashMap<OsString, OsString>,
+}
+
+/// An in-memory [`SystemEnv`] implementation for deterministic unit tests.
+///
+/// Tracks a virtual current directory and a map of environment variables
+/// without touching the real process, so a [`Jailer`]/[`EnvJailer`]'s
+/// restore-and-cleanup logic can be unit-tested without the `unsafe` and
+/// non-concurrent constraints that come with mutating the real environment.
+/// Wrap it in an [`Arc`] to share one virtual state between a [`Jailer`] and
+/// an [`EnvJailer`] built from the same [`SystemEnv`].
+#[derive(Debug)]
+pub struct TestSystemEnv {
+    state: Mutex<TestSystemEnvState>,
+}
+
+impl TestSystemEnv {
+    /// Create a new [`TestSystemEnv`] starting at `current_dir` with the
+    /// given initial environment variables.
+    #[must_use]
+    pub fn new<P>(current_dir: P, vars: HashMap<OsString, OsString>) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            state: Mutex::new(TestSystemEnvState {
+                current_dir: current_dir.into(),
+                vars,
+            }),
+        }
+    }
+}
+
+impl SystemEnv for TestSystemEnv {
+    fn current_dir(&self) -> Result<PathBuf, std::io::Error> {
+        Ok(self.state.lock().current_dir.clone())
+    }
+
+    fn set_current_dir(&self, path: &Path) -> Result<(), std::io::Error> {
+        self.state.lock().current_dir = path.to_path_buf();
+        Ok(())
+    }
+
+    fn vars_os(&self) -> HashMap<OsString, OsString> {
+        self.state.lock().vars.clone()
+    }
+
+    fn var_os(&self, key: &OsStr) -> Option<OsString> {
+        self.state.lock().vars.get(key).cloned()
+    }
+
+    unsafe fn set_var(&self, key: &OsStr, value: &OsStr) {
+        self.state
+            .lock()
+            .vars
+            .insert(key.to_os_string(), value.to_os_string());
+    }
+
+    unsafe fn remove_var(&self, key: &OsStr) {
+        self.state.lock().vars.remove(key);
+    }
+}
+
 /// [`Jailer`] struct which creates a jail environment.
 ///
 /// [`Jailer`] creates a temporary directory and changes the current working
 /// directory to it. On drop or manual close, it restores the original working
 /// directory and deletes the temporary directory.
+/// A [`Jailer`] built with [`JailerBuilder::keep`] set keeps it instead.
+///
+/// It uses a global, reentrant lock to ensure only one thread is mutating
+/// the process's current directory at a time; nested [`Jailer`]s (or
+/// [`push_dir`]/[`push_env`] scopes) created on the thread that already
+/// holds the lock do not deadlock. Nested `Jailer`s must still be closed (or
+/// dropped) in strict LIFO order, like [`DirGuard`]/[`EnvGuard`]; out-of-order
+/// closes are caught by a debug assertion (see [`Jailer::close`]).
 ///
-/// It uses a global mutex to ensure only one `Jailer` is active at a time
-/// across threads.
-pub struct Jailer {
+/// Generic over a [`SystemEnv`] implementation (defaulting to
+/// [`StdSystemEnv`]) so the directory restore/cleanup logic can be
+/// unit-tested against [`TestSystemEnv`] instead of the real process.
+pub struct Jailer<E = StdSystemEnv>
+where
+    E: SystemEnv,
+{
     temp_directory: Option<TempDir>,
     original_directory: PathBuf,
-    _lock: ArcMutexGuard<RawMutex, ()>,
+    entered_directory: PathBuf,
+    _lock: ReentrantLockGuard,
     is_closed: bool,
+    keep: bool,
+    env: E,
+    initial_snapshot: Option<HashMap<PathBuf, FileStamp>>,
 }
 
-impl Jailer {
-    /// Create a new [`Jailer`].
+impl Jailer<StdSystemEnv> {
+    /// Create a new [`Jailer`] backed by the real OS current directory.
     ///
     /// This will:
-    /// - Lock globally to prevent concurrent instances.
+    /// - Lock globally to prevent concurrent instances on other threads.
     /// - Create a temporary directory.
     /// - Change the current directory to that temp dir.
     ///
@@ -81,15 +356,76 @@ impl Jailer {
     /// assert_ne!(inside_jailer_directory, after_jailer_directory);
     /// ```
     pub fn new() -> Result<Self, std::io::Error> {
-        let lock = initialize_or_get_mutex().lock_arc();
+        Self::with_system_env(StdSystemEnv)
+    }
+
+    /// Start building a [`Jailer`] with a configurable temp-directory
+    /// location, seed files, and keep-on-close behavior.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jailer::Jailer;
+    ///
+    /// let jailer = Jailer::builder().build().unwrap();
+    /// jailer.close().unwrap();
+    /// ```
+    #[must_use]
+    pub fn builder() -> JailerBuilder<StdSystemEnv> {
+        JailerBuilder::new()
+    }
+}
+
+impl<E> Jailer<E>
+where
+    E: SystemEnv,
+{
+    /// Create a new [`Jailer`] driven by a custom [`SystemEnv`]
+    /// implementation.
+    ///
+    /// Use [`TestSystemEnv`] in tests to exercise jail behavior
+    /// deterministically, without touching the real process's current
+    /// directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The temporary directory cannot be created.
+    /// - Changing the current directory fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jailer::{Jailer, SystemEnv, TestSystemEnv};
+    /// use std::sync::Arc;
+    ///
+    /// let env = Arc::new(TestSystemEnv::new("/original", std::collections::HashMap::new()));
+    /// let jailer = Jailer::with_system_env(env.clone()).unwrap();
+    ///
+    /// // The virtual current directory moved into the (real) jail directory.
+    /// assert_eq!(env.current_dir().unwrap(), jailer.jail_directory());
+    /// assert_eq!(jailer.original_directory().as_path(), std::path::Path::new("/original"));
+    ///
+    /// jailer.close().unwrap();
+    ///
+    /// // Closing restored the virtual current directory.
+    /// assert_eq!(env.current_dir().unwrap(), std::path::Path::new("/original"));
+    /// ```
+    pub fn with_system_env(env: E) -> Result<Self, std::io::Error> {
+        let lock = acquire_reentrant_lock();
         let temp_dir = TempDir::new()?;
-        let original_directory = std::env::current_dir()?;
-        std::env::set_current_dir(&temp_dir)?;
+        let original_directory = env.current_dir()?;
+        env.set_current_dir(temp_dir.path())?;
+        let entered_directory = env.current_dir()?;
         Ok(Self {
             temp_directory: Some(temp_dir),
             original_directory,
+            entered_directory,
             _lock: lock,
             is_closed: false,
+            keep: false,
+            env,
+            initial_snapshot: None,
         })
     }
 
@@ -114,38 +450,539 @@ impl Jailer {
         &self.original_directory
     }
 
+    /// Get a reference to the jail's temporary directory.
+    ///
+    /// This is the directory the current directory was changed to when the
+    /// [`Jailer`] was created.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jailer::Jailer;
+    ///
+    /// let jailer = Jailer::new().unwrap();
+    /// assert_eq!(jailer.jail_directory(), std::env::current_dir().unwrap());
+    /// jailer.close().unwrap();
+    /// ```
+    #[must_use]
+    pub fn jail_directory(&self) -> &Path {
+        self.temp_directory
+            .as_ref()
+            .expect("Jailer temp directory missing")
+            .path()
+    }
+
+    /// Build a [`JailedCommand`] that runs `program` with its working
+    /// directory pinned to this jail's temporary directory.
+    ///
+    /// The returned builder mirrors [`std::process::Command`] and guarantees
+    /// that `output`, `status`, and `spawn` always launch the child inside
+    /// the jail, even if the parent process's current directory has drifted
+    /// since the jail was created. This lets callers invoke compilers, git,
+    /// or other shell tools inside the isolated directory without manually
+    /// threading the jail's path into every [`std::process::Command`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use jailer::Jailer;
+    ///
+    /// let jailer = Jailer::new().unwrap();
+    ///
+    /// let output = jailer.command("git").arg("status").output().unwrap();
+    /// assert!(output.status.success());
+    ///
+    /// jailer.close().unwrap();
+    /// ```
+    #[must_use]
+    pub fn command<S>(&self, program: S) -> JailedCommand
+    where
+        S: AsRef<OsStr>,
+    {
+        JailedCommand::new(program, self.jail_directory().to_path_buf())
+    }
+
+    /// Snapshot the jail's current filesystem state and diff it against the
+    /// snapshot taken when the jail was created, reporting which files (by
+    /// path relative to the jail directory) were created, modified, or
+    /// removed during the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if change tracking was not enabled via
+    /// [`JailerBuilder::track_changes`], or if the jail directory cannot be
+    /// walked.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jailer::Jailer;
+    ///
+    /// let jailer = Jailer::builder().track_changes(true).build().unwrap();
+    /// std::fs::write("new_file.txt", b"hello").unwrap();
+    ///
+    /// let changes = jailer.changes().unwrap();
+    /// assert!(changes.created().contains(std::path::Path::new("new_file.txt")));
+    ///
+    /// jailer.close().unwrap();
+    /// ```
+    pub fn changes(&self) -> Result<FsDiff, std::io::Error> {
+        let initial_snapshot = self.initial_snapshot.as_ref().ok_or_else(|| {
+            std::io::Error::other(
+                "change tracking is not enabled; build with JailerBuilder::track_changes(true)",
+            )
+        })?;
+        let current_snapshot = snapshot_dir(self.jail_directory())?;
+        Ok(diff_snapshots(initial_snapshot, &current_snapshot))
+    }
+
+    /// Close the jail like [`Jailer::close`], additionally returning the
+    /// [`FsDiff`] of filesystem changes made during the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if change tracking was not enabled, or if closing
+    /// the jail fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jailer::Jailer;
+    ///
+    /// let jailer = Jailer::builder().track_changes(true).build().unwrap();
+    /// std::fs::write("new_file.txt", b"hello").unwrap();
+    ///
+    /// let changes = jailer.close_with_changes().unwrap();
+    /// assert!(changes.created().contains(std::path::Path::new("new_file.txt")));
+    /// ```
+    pub fn close_with_changes(self) -> Result<FsDiff, std::io::Error> {
+        let changes = self.changes()?;
+        self.close()?;
+        Ok(changes)
+    }
+
     /// Closes the [`Jailer`] and performs cleanup.
     ///
     /// This method:
     /// - Changes back to the original working directory.
     /// - Deletes the temporary directory.
+    ///   Skipped if the jail was built with [`JailerBuilder::keep`] set.
     /// - Releases the global lock.
     ///
     /// It consumes `self`, so the jailer cannot be used afterward.
     ///
+    /// Nested `Jailer`s on the same thread (made possible by the reentrant
+    /// lock) must be closed in strict LIFO order; a debug assertion detects
+    /// out-of-order closes by comparing the current directory against the
+    /// one this `Jailer` entered.
+    ///
     /// # Errors
     ///
     /// Returns an error if changing the directory or deleting the temp dir
     /// fails.
     pub fn close(mut self) -> Result<(), std::io::Error> {
-        std::env::set_current_dir(self.original_directory.as_path())?;
+        self.assert_lifo_order("closed");
+        self.env.set_current_dir(self.original_directory.as_path())?;
         if let Some(temp) = self.temp_directory.take() {
-            temp.close()?;
+            if self.keep {
+                let _ = temp.keep();
+            } else {
+                temp.close()?;
+            }
         }
         self.is_closed = true;
         Ok(())
     }
+
+    /// Debug-assert that the current directory still matches the one this
+    /// `Jailer` entered, catching nested `Jailer`s closed/dropped out of
+    /// strict LIFO order before their cwd is blindly restored. A no-op in
+    /// release builds.
+    fn assert_lifo_order(&self, verb: &str) {
+        if cfg!(debug_assertions)
+            && let Ok(current) = self.env.current_dir()
+        {
+            debug_assert_eq!(
+                current, self.entered_directory,
+                "Jailer {verb} out of LIFO order: expected cwd {:?}, found {:?}",
+                self.entered_directory, current
+            );
+        }
+    }
 }
 
-impl Drop for Jailer {
+impl<E> Drop for Jailer<E>
+where
+    E: SystemEnv,
+{
     fn drop(&mut self) {
         if !self.is_closed {
-            std::env::set_current_dir(self.original_directory.as_path()).ok();
+            self.assert_lifo_order("dropped");
+            self.env
+                .set_current_dir(self.original_directory.as_path())
+                .ok();
             if let Some(temp) = self.temp_directory.take() {
-                temp.close().ok();
+                if self.keep {
+                    let _ = temp.keep();
+                } else {
+                    temp.close().ok();
+                }
+            }
+        }
+    }
+}
+
+/// Recursively copy the contents of `src` into `dst`, creating
+/// sub-directories in `dst` as needed.
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let dst_path = dst.join(entry.file_name());
+        if file_type.is_dir() {
+            std::fs::create_dir_all(&dst_path)?;
+            copy_dir_recursive(&entry.path(), &dst_path)?;
+        } else if file_type.is_file() {
+            std::fs::copy(entry.path(), &dst_path)?;
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(entry.path())?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(target, &dst_path)?;
+            #[cfg(windows)]
+            {
+                if target.is_dir() {
+                    std::os::windows::fs::symlink_dir(target, &dst_path)?;
+                } else {
+                    std::os::windows::fs::symlink_file(target, &dst_path)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A snapshot of one file's size and modification time, used to detect
+/// whether it changed between two snapshots of a jail directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<std::time::SystemTime>,
+}
+
+/// Recursively snapshot every regular file under `root`, keyed by path
+/// relative to `root`.
+fn snapshot_dir(root: &Path) -> Result<HashMap<PathBuf, FileStamp>, std::io::Error> {
+    let mut snapshot = HashMap::new();
+    snapshot_dir_into(root, root, &mut snapshot)?;
+    Ok(snapshot)
+}
+
+fn snapshot_dir_into(
+    root: &Path,
+    dir: &Path,
+    out: &mut HashMap<PathBuf, FileStamp>,
+) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            snapshot_dir_into(root, &path, out)?;
+        } else if file_type.is_file() {
+            let metadata = entry.metadata()?;
+            let relative = path
+                .strip_prefix(root)
+                .expect("entry path is always under root")
+                .to_path_buf();
+            out.insert(
+                relative,
+                FileStamp {
+                    len: metadata.len(),
+                    modified: metadata.modified().ok(),
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Diff two snapshots taken with [`snapshot_dir`], classifying paths as
+/// created, modified (size or modification time changed), or removed.
+fn diff_snapshots(
+    before: &HashMap<PathBuf, FileStamp>,
+    after: &HashMap<PathBuf, FileStamp>,
+) -> FsDiff {
+    let mut created = HashSet::new();
+    let mut modified = HashSet::new();
+    let mut removed = HashSet::new();
+
+    for (path, after_stamp) in after {
+        match before.get(path) {
+            None => {
+                created.insert(path.clone());
             }
+            Some(before_stamp) if before_stamp != after_stamp => {
+                modified.insert(path.clone());
+            }
+            Some(_) => {}
+        }
+    }
+    for path in before.keys() {
+        if !after.contains_key(path) {
+            removed.insert(path.clone());
         }
     }
+
+    FsDiff {
+        created,
+        modified,
+        removed,
+    }
+}
+
+/// A structured diff of the filesystem changes made inside a jail, reported
+/// by [`Jailer::changes`]/[`Jailer::close_with_changes`].
+///
+/// Paths are relative to the jail directory. A file is considered modified
+/// if its size or modification time differs from the snapshot taken when
+/// the jail was created.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FsDiff {
+    created: HashSet<PathBuf>,
+    modified: HashSet<PathBuf>,
+    removed: HashSet<PathBuf>,
+}
+
+impl FsDiff {
+    /// Paths (relative to the jail directory) that did not exist in the
+    /// initial snapshot.
+    #[must_use]
+    pub fn created(&self) -> &HashSet<PathBuf> {
+        &self.created
+    }
+
+    /// Paths (relative to the jail directory) whose size or modification
+    /// time changed since the initial snapshot.
+    #[must_use]
+    pub fn modified(&self) -> &HashSet<PathBuf> {
+        &self.modified
+    }
+
+    /// Paths (relative to the jail directory) present in the initial
+    /// snapshot but no longer present.
+    #[must_use]
+    pub fn removed(&self) -> &HashSet<PathBuf> {
+        &self.removed
+    }
+
+    /// Returns `true` if no files were created, modified, or removed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Builder for [`Jailer`] with a configurable temp-directory location, seed
+/// files, and keep-on-close behavior.
+///
+/// Created via [`Jailer::builder`]. Mirrors the configurable style of
+/// [`tempfile::Builder`] (custom parent directory, name prefix) while adding
+/// jail-specific options such as seeding the fresh directory from a fixture
+/// tree before entering it. Generic over a [`SystemEnv`] implementation,
+/// defaulting to [`StdSystemEnv`]; swap it with [`JailerBuilder::system_env`].
+pub struct JailerBuilder<E = StdSystemEnv>
+where
+    E: SystemEnv,
+{
+    parent_directory: Option<PathBuf>,
+    prefix: Option<String>,
+    keep: bool,
+    make_dir_if_needed: bool,
+    seed_from: Option<PathBuf>,
+    track_changes: bool,
+    env: E,
+}
+
+impl JailerBuilder<StdSystemEnv> {
+    fn new() -> Self {
+        Self::with_system_env(StdSystemEnv)
+    }
+}
+
+impl<E> JailerBuilder<E>
+where
+    E: SystemEnv,
+{
+    /// Start a [`JailerBuilder`] driven by a custom [`SystemEnv`]
+    /// implementation instead of [`StdSystemEnv`].
+    #[must_use]
+    pub fn with_system_env(env: E) -> Self {
+        Self {
+            parent_directory: None,
+            prefix: None,
+            keep: false,
+            make_dir_if_needed: false,
+            seed_from: None,
+            track_changes: false,
+            env,
+        }
+    }
+
+    /// Swap this builder's [`SystemEnv`] implementation for `env`, keeping
+    /// every other option configured so far.
+    #[must_use]
+    pub fn system_env<F>(self, env: F) -> JailerBuilder<F>
+    where
+        F: SystemEnv,
+    {
+        JailerBuilder {
+            parent_directory: self.parent_directory,
+            prefix: self.prefix,
+            keep: self.keep,
+            make_dir_if_needed: self.make_dir_if_needed,
+            seed_from: self.seed_from,
+            track_changes: self.track_changes,
+            env,
+        }
+    }
+
+    /// Create the temp directory inside `path` instead of the system temp
+    /// directory, mirroring [`tempfile::Builder::tempdir_in`].
+    #[must_use]
+    pub fn parent_directory<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.parent_directory = Some(path.into());
+        self
+    }
+
+    /// Set a name prefix for the temp directory.
+    #[must_use]
+    pub fn prefix<S>(mut self, prefix: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// If `true`, skip deleting the temp directory on close/drop, so a
+    /// failing test's sandbox can be inspected afterward.
+    #[must_use]
+    pub fn keep(mut self, keep: bool) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// If `true`, create [`JailerBuilder::parent_directory`] (and any missing
+    /// ancestors) before creating the temp directory inside it.
+    #[must_use]
+    pub fn make_dir_if_needed(mut self, make_dir_if_needed: bool) -> Self {
+        self.make_dir_if_needed = make_dir_if_needed;
+        self
+    }
+
+    /// Recursively copy the contents of `src` into the fresh temp directory
+    /// before changing into it, so the jail starts pre-populated with
+    /// fixture files.
+    #[must_use]
+    pub fn seed_from<P>(mut self, src: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.seed_from = Some(src.into());
+        self
+    }
+
+    /// If `true`, snapshot the temp directory when the jail is created so
+    /// that [`Jailer::changes`]/[`Jailer::close_with_changes`] can later
+    /// report which files were created, modified, or removed.
+    #[must_use]
+    pub fn track_changes(mut self, track_changes: bool) -> Self {
+        self.track_changes = track_changes;
+        self
+    }
+
+    /// Build the [`Jailer`], applying every option configured so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parent directory cannot be created, the temp
+    /// directory cannot be created, seeding fails, or changing the current
+    /// directory fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jailer::Jailer;
+    ///
+    /// // Seed the jail from a fixture directory and keep it around on
+    /// // close so it can be inspected afterward.
+    /// let fixture = std::env::temp_dir().join("jailer-builder-doctest-fixture");
+    /// std::fs::create_dir_all(&fixture).unwrap();
+    /// std::fs::write(fixture.join("input.txt"), b"fixture data").unwrap();
+    ///
+    /// let jailer = Jailer::builder()
+    ///     .seed_from(&fixture)
+    ///     .keep(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let jail_directory = jailer.jail_directory().to_path_buf();
+    /// assert!(jail_directory.join("input.txt").exists());
+    ///
+    /// jailer.close().unwrap();
+    ///
+    /// // The temp directory was kept, since `keep(true)` was set.
+    /// assert!(jail_directory.join("input.txt").exists());
+    ///
+    /// # std::fs::remove_dir_all(&fixture).unwrap();
+    /// # std::fs::remove_dir_all(&jail_directory).unwrap();
+    /// ```
+    pub fn build(self) -> Result<Jailer<E>, std::io::Error> {
+        let lock = acquire_reentrant_lock();
+
+        if let Some(parent) = &self.parent_directory
+            && self.make_dir_if_needed
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut builder = tempfile::Builder::new();
+        if let Some(prefix) = &self.prefix {
+            builder.prefix(prefix);
+        }
+        let temp_dir = match &self.parent_directory {
+            Some(parent) => builder.tempdir_in(parent)?,
+            None => builder.tempdir()?,
+        };
+
+        if let Some(src) = &self.seed_from {
+            copy_dir_recursive(src, temp_dir.path())?;
+        }
+
+        let initial_snapshot = if self.track_changes {
+            Some(snapshot_dir(temp_dir.path())?)
+        } else {
+            None
+        };
+
+        let original_directory = self.env.current_dir()?;
+        self.env.set_current_dir(temp_dir.path())?;
+        let entered_directory = self.env.current_dir()?;
+
+        Ok(Jailer {
+            temp_directory: Some(temp_dir),
+            original_directory,
+            entered_directory,
+            _lock: lock,
+            is_closed: false,
+            keep: self.keep,
+            env: self.env,
+            initial_snapshot,
+        })
+    }
 }
 
 /// [`EnvJailer`] struct which creates a jail environment with environment
@@ -163,15 +1000,23 @@ impl Drop for Jailer {
 /// [`std::env::remove_var`] is considered unsafe due to potential race
 /// conditions in multi-threaded programs. Therefore, methods like
 /// [`EnvJailer::close`] are marked as `unsafe`.
-pub struct EnvJailer {
-    jailer: Option<Jailer>,
+///
+/// Generic over a [`SystemEnv`] implementation (defaulting to
+/// [`StdSystemEnv`]), shared with the inner [`Jailer`], so restore/cleanup
+/// logic can be unit-tested against [`TestSystemEnv`].
+pub struct EnvJailer<E = StdSystemEnv>
+where
+    E: SystemEnv + Clone,
+{
+    jailer: Option<Jailer<E>>,
     original_directory: PathBuf,
     original_env_vars_os: HashMap<OsString, OsString>,
     preserved_env_vars_os: HashSet<OsString>,
+    env: E,
 }
 
-impl EnvJailer {
-    /// Create a new [`EnvJailer`].
+impl EnvJailer<StdSystemEnv> {
+    /// Create a new [`EnvJailer`] backed by the real OS environment.
     ///
     /// This captures the current environment variables and working directory,
     /// then initializes a new [`Jailer`].
@@ -201,8 +1046,56 @@ impl EnvJailer {
     /// assert_eq!(std::env::current_dir().unwrap(), original_directory);
     /// ```
     pub fn new() -> Result<Self, std::io::Error> {
-        let original_env_vars_os = std::env::vars_os().collect();
-        let jailer = Jailer::new()?;
+        Self::with_system_env(StdSystemEnv)
+    }
+}
+
+impl<E> EnvJailer<E>
+where
+    E: SystemEnv + Clone,
+{
+    /// Create a new [`EnvJailer`] driven by a custom [`SystemEnv`]
+    /// implementation, sharing it with the inner [`Jailer`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying [`Jailer`] cannot be created.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use jailer::{EnvJailer, SystemEnv, TestSystemEnv};
+    /// use std::collections::HashMap;
+    /// use std::ffi::{OsStr, OsString};
+    /// use std::sync::Arc;
+    ///
+    /// let mut initial_vars = HashMap::new();
+    /// initial_vars.insert(OsString::from("KEEP_ME"), OsString::from("original"));
+    /// initial_vars.insert(OsString::from("DROP_ME"), OsString::from("original"));
+    /// let env = Arc::new(TestSystemEnv::new("/original", initial_vars));
+    ///
+    /// let mut env_jailer = EnvJailer::with_system_env(env.clone()).unwrap();
+    /// env_jailer.set_preserved_env("KEEP_ME");
+    ///
+    /// unsafe {
+    ///     env.set_var(OsStr::new("KEEP_ME"), OsStr::new("mutated"));
+    ///     env.remove_var(OsStr::new("DROP_ME"));
+    ///     env.set_var(OsStr::new("NEW_VAR"), OsStr::new("added"));
+    /// }
+    ///
+    /// unsafe {
+    ///     env_jailer.close().unwrap();
+    /// }
+    ///
+    /// // Preserved vars keep their mutated value; everything else reverts to
+    /// // what it was when the `EnvJailer` was created.
+    /// assert_eq!(env.var_os(OsStr::new("KEEP_ME")), Some(OsString::from("mutated")));
+    /// assert_eq!(env.var_os(OsStr::new("DROP_ME")), Some(OsString::from("original")));
+    /// assert_eq!(env.var_os(OsStr::new("NEW_VAR")), None);
+    /// ```
+    pub fn with_system_env(env: E) -> Result<Self, std::io::Error> {
+        let original_env_vars_os = env.vars_os();
+        let jailer = Jailer::with_system_env(env.clone())?;
         let original_dir = jailer.original_directory().clone();
 
         Ok(Self {
@@ -210,6 +1103,7 @@ impl EnvJailer {
             original_directory: original_dir,
             original_env_vars_os,
             preserved_env_vars_os: HashSet::new(),
+            env,
         })
     }
 
@@ -332,18 +1226,62 @@ impl EnvJailer {
         &self.preserved_env_vars_os
     }
 
+    /// Build a [`JailedCommand`] that runs `program` with its working
+    /// directory pinned to this jail's temporary directory and its
+    /// environment limited to the variables marked with
+    /// [`EnvJailer::set_preserved_env`].
+    ///
+    /// Any other environment variables currently set on the process are not
+    /// passed to the child. Use [`JailedCommand::env`]/[`JailedCommand::envs`]
+    /// on the returned builder to add further variables on top of the
+    /// preserved set.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use jailer::EnvJailer;
+    ///
+    /// let mut env_jailer = EnvJailer::new().unwrap();
+    /// env_jailer.set_preserved_env("PATH");
+    ///
+    /// let output = env_jailer.command("git").arg("status").output().unwrap();
+    /// assert!(output.status.success());
+    ///
+    /// unsafe {
+    ///     env_jailer.close().unwrap();
+    /// }
+    /// ```
+    #[must_use]
+    pub fn command<S>(&self, program: S) -> JailedCommand
+    where
+        S: AsRef<OsStr>,
+    {
+        let jailer = self
+            .jailer
+            .as_ref()
+            .expect("EnvJailer used after close");
+        let mut command = jailer.command(program);
+        command.env_clear();
+        for key in &self.preserved_env_vars_os {
+            if let Some(value) = self.env.var_os(key) {
+                command.env(key, value);
+            }
+        }
+        command
+    }
+
     unsafe fn revert_env_vars(&self) {
-        for key in std::env::vars_os().collect::<HashMap<_, _>>().keys() {
+        for key in self.env.vars_os().keys() {
             if !self.preserved_env_vars_os.contains(key) {
                 unsafe {
-                    std::env::remove_var(key);
+                    self.env.remove_var(key);
                 }
             }
         }
         for (key, value) in &self.original_env_vars_os {
             if !self.preserved_env_vars_os.contains(key) {
                 unsafe {
-                    std::env::set_var(key, value);
+                    self.env.set_var(key, value);
                 }
             }
         }
@@ -359,6 +1297,10 @@ impl EnvJailer {
     ///
     /// It consumes `self`, so the jailer cannot be used afterward.
     ///
+    /// Nested `EnvJailer`s on the same thread must be closed in strict LIFO
+    /// order; the underlying [`Jailer::close`] debug-asserts this by
+    /// comparing the current directory before restoring it.
+    ///
     /// # Errors
     ///
     /// Returns an error if the underlying [`Jailer::close`] fails.
@@ -378,7 +1320,10 @@ impl EnvJailer {
     }
 }
 
-impl Drop for EnvJailer {
+impl<E> Drop for EnvJailer<E>
+where
+    E: SystemEnv + Clone,
+{
     fn drop(&mut self) {
         if self.jailer.is_some() {
             unsafe {
@@ -388,6 +1333,286 @@ impl Drop for EnvJailer {
     }
 }
 
+/// A [`std::process::Command`] builder pinned to a jail's temporary
+/// directory.
+///
+/// Returned by [`Jailer::command`] and [`EnvJailer::command`]. It mirrors the
+/// familiar `std::process::Command` surface (`arg`, `args`, `env`, `envs`,
+/// `env_remove`, `env_clear`) while guaranteeing that `output`, `status`, and
+/// `spawn` always launch the child with its working directory set to the
+/// jail, regardless of what the parent process's current directory has
+/// drifted to in the meantime.
+pub struct JailedCommand {
+    inner: Command,
+    jail_directory: PathBuf,
+}
+
+impl JailedCommand {
+    fn new<S>(program: S, jail_directory: PathBuf) -> Self
+    where
+        S: AsRef<OsStr>,
+    {
+        let mut inner = Command::new(program);
+        inner.current_dir(&jail_directory);
+        Self {
+            inner,
+            jail_directory,
+        }
+    }
+
+    /// Add a single argument, mirroring [`std::process::Command::arg`].
+    pub fn arg<S>(&mut self, arg: S) -> &mut Self
+    where
+        S: AsRef<OsStr>,
+    {
+        self.inner.arg(arg);
+        self
+    }
+
+    /// Add multiple arguments, mirroring [`std::process::Command::args`].
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    /// Set an environment variable, mirroring [`std::process::Command::env`].
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.env(key, val);
+        self
+    }
+
+    /// Set multiple environment variables, mirroring
+    /// [`std::process::Command::envs`].
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.envs(vars);
+        self
+    }
+
+    /// Remove an environment variable, mirroring
+    /// [`std::process::Command::env_remove`].
+    pub fn env_remove<K>(&mut self, key: K) -> &mut Self
+    where
+        K: AsRef<OsStr>,
+    {
+        self.inner.env_remove(key);
+        self
+    }
+
+    /// Clear all environment variables, mirroring
+    /// [`std::process::Command::env_clear`].
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.inner.env_clear();
+        self
+    }
+
+    /// Get the jail directory this command is pinned to.
+    #[must_use]
+    pub fn jail_directory(&self) -> &Path {
+        &self.jail_directory
+    }
+
+    /// Run the command, waiting for it to finish and collecting its output,
+    /// mirroring [`std::process::Command::output`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails to spawn or its output cannot be
+    /// collected.
+    pub fn output(&mut self) -> std::io::Result<Output> {
+        self.inner.current_dir(&self.jail_directory);
+        self.inner.output()
+    }
+
+    /// Run the command, waiting for it to finish, mirroring
+    /// [`std::process::Command::status`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails to spawn or run.
+    pub fn status(&mut self) -> std::io::Result<ExitStatus> {
+        self.inner.current_dir(&self.jail_directory);
+        self.inner.status()
+    }
+
+    /// Spawn the command as a child process, mirroring
+    /// [`std::process::Command::spawn`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails to spawn.
+    pub fn spawn(&mut self) -> std::io::Result<Child> {
+        self.inner.current_dir(&self.jail_directory);
+        self.inner.spawn()
+    }
+}
+
+/// A scope guard returned by [`push_dir`] that restores the previous current
+/// directory on drop.
+///
+/// Guards created by nested calls to [`push_dir`] share the thread's
+/// reentrant lock (see [`acquire_reentrant_lock`]) and must be dropped in
+/// strict LIFO order; a debug assertion detects out-of-order drops by
+/// comparing the current directory against the one this guard entered.
+pub struct DirGuard {
+    previous_directory: PathBuf,
+    entered_directory: PathBuf,
+    _lock: ReentrantLockGuard,
+}
+
+impl Drop for DirGuard {
+    fn drop(&mut self) {
+        if let Ok(current) = std::env::current_dir() {
+            debug_assert_eq!(
+                current, self.entered_directory,
+                "DirGuard dropped out of LIFO order: expected cwd {:?}, found {:?}",
+                self.entered_directory, current
+            );
+        }
+        std::env::set_current_dir(&self.previous_directory).ok();
+    }
+}
+
+/// Temporarily change the current directory to `path`, restoring the
+/// previous one when the returned [`DirGuard`] is dropped.
+///
+/// This can be called standalone, or nested inside an existing [`Jailer`] to
+/// scope a sub-directory change within the jail; nested scopes on the same
+/// thread share the global lock instead of deadlocking (see
+/// [`acquire_reentrant_lock`]).
+///
+/// # Errors
+///
+/// Returns an error if the current directory cannot be read or `path` cannot
+/// be entered.
+///
+/// # Example
+///
+/// ```rust
+/// use jailer::{push_dir, Jailer};
+///
+/// let jailer = Jailer::new().unwrap();
+/// let jail_directory = std::env::current_dir().unwrap();
+///
+/// std::fs::create_dir("sub").unwrap();
+/// {
+///     let _guard = push_dir("sub").unwrap();
+///     assert_eq!(std::env::current_dir().unwrap(), jail_directory.join("sub"));
+/// }
+/// assert_eq!(std::env::current_dir().unwrap(), jail_directory);
+///
+/// jailer.close().unwrap();
+/// ```
+pub fn push_dir<P>(path: P) -> Result<DirGuard, std::io::Error>
+where
+    P: AsRef<Path>,
+{
+    let lock = acquire_reentrant_lock();
+    let previous_directory = std::env::current_dir()?;
+    std::env::set_current_dir(path)?;
+    let entered_directory = std::env::current_dir()?;
+    Ok(DirGuard {
+        previous_directory,
+        entered_directory,
+        _lock: lock,
+    })
+}
+
+/// A scope guard returned by [`push_env`] that restores the previous value
+/// (or absence) of an environment variable on drop.
+///
+/// Guards created by nested calls to [`push_env`] share the thread's
+/// reentrant lock (see [`acquire_reentrant_lock`]) and must be dropped in
+/// strict LIFO order; a debug assertion detects out-of-order drops by
+/// comparing the variable's current value against the one this guard set.
+pub struct EnvGuard {
+    key: OsString,
+    previous_value: Option<OsString>,
+    entered_value: OsString,
+    _lock: ReentrantLockGuard,
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        let current = std::env::var_os(&self.key);
+        debug_assert_eq!(
+            current.as_deref(),
+            Some(self.entered_value.as_os_str()),
+            "EnvGuard for {:?} dropped out of LIFO order: expected {:?}, found {:?}",
+            self.key,
+            self.entered_value,
+            current
+        );
+        unsafe {
+            match &self.previous_value {
+                Some(value) => std::env::set_var(&self.key, value),
+                None => std::env::remove_var(&self.key),
+            }
+        }
+    }
+}
+
+/// Temporarily set an environment variable to `value`, restoring its
+/// previous value (or removing it if it was unset) when the returned
+/// [`EnvGuard`] is dropped.
+///
+/// This can be called standalone, or nested inside an existing [`Jailer`]/
+/// [`EnvJailer`] to override a single variable for a sub-scope; nested
+/// scopes on the same thread share the global lock instead of deadlocking
+/// (see [`acquire_reentrant_lock`]).
+///
+/// # Safety
+///
+/// This function calls [`std::env::set_var`], which is unsafe due to
+/// possible data races in concurrent contexts.
+///
+/// # Example
+///
+/// ```rust
+/// use jailer::push_env;
+///
+/// unsafe {
+///     std::env::set_var("JAILER_SCOPE_KEY", "outer");
+/// }
+///
+/// unsafe {
+///     let _guard = push_env("JAILER_SCOPE_KEY", "inner");
+///     assert_eq!(std::env::var("JAILER_SCOPE_KEY").unwrap(), "inner");
+/// }
+///
+/// assert_eq!(std::env::var("JAILER_SCOPE_KEY").unwrap(), "outer");
+/// ```
+pub unsafe fn push_env<K, V>(key: K, value: V) -> EnvGuard
+where
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let lock = acquire_reentrant_lock();
+    let key = key.as_ref().to_os_string();
+    let previous_value = std::env::var_os(&key);
+    unsafe {
+        std::env::set_var(&key, &value);
+    }
+    EnvGuard {
+        key,
+        previous_value,
+        entered_value: value.as_ref().to_os_string(),
+        _lock: lock,
+    }
+}
+
 /// Run a closure inside a [`Jailer`] environment.
 ///
 /// This function creates a [`Jailer`], runs the provided closure, and ensures